@@ -0,0 +1,192 @@
+// https://docs.microsoft.com/en-us/typography/opentype/spec/otff#calculating-checksums
+
+use crate::parser::{Stream, TrySlice};
+use crate::{Font, Error, Result};
+
+/// The magic number used to verify the whole-file checksum stored in `head`.
+const CHECKSUM_MAGIC: u32 = 0xB1B0_AFBA;
+
+// The `checkSumAdjustment` field lives 8 bytes into the `head` table.
+const HEAD_CHECKSUM_ADJUSTMENT_OFFSET: usize = 8;
+
+const HEAD_TABLE_TAG: u32 = 0x68656164; // 'head'
+
+struct TableRecord {
+    tag: u32,
+    check_sum: u32,
+    offset: u32,
+    length: u32,
+}
+
+impl<'a> Font<'a> {
+    /// Computes the checksum of a single table and compares it against
+    /// the `checkSum` stored in its table-directory record.
+    ///
+    /// Returns the table's own computed checksum on success.
+    pub fn table_checksum(&self, tag: u32) -> Result<u32> {
+        let record = self.table_record(tag)?;
+        let sum = table_checksum_for(self.data, &record)?;
+        if sum != record.check_sum {
+            return Err(Error::InvalidTableChecksum(tag));
+        }
+
+        Ok(sum)
+    }
+
+    /// Validates the checksum of every table in the font, as well as
+    /// the whole-file checksum stored in `head.checkSumAdjustment`.
+    pub fn validate_checksums(&self) -> Result<bool> {
+        self.for_each_table_record(|record| {
+            if table_checksum_for(self.data, &record)? != record.check_sum {
+                return Err(Error::InvalidTableChecksum(record.tag));
+            }
+
+            Ok(())
+        })?;
+
+        let head = self.table_record(HEAD_TABLE_TAG)?;
+        let stored: u32 =
+            Stream::read_at(self.data, head.offset as usize + HEAD_CHECKSUM_ADJUSTMENT_OFFSET)?;
+
+        // Checksumming the whole file embeds `checkSumAdjustment` itself,
+        // so subtract it back out before comparing against the magic number.
+        let file_sum = table_checksum(self.data).wrapping_sub(stored);
+        let adjustment = CHECKSUM_MAGIC.wrapping_sub(file_sum);
+
+        Ok(adjustment == stored)
+    }
+
+    fn for_each_table_record(&self, mut f: impl FnMut(TableRecord) -> Result<()>) -> Result<()> {
+        let mut s = Stream::new(self.data);
+        s.skip::<u32>(); // sfnt version
+        let num_tables: u16 = s.read()?;
+        s.advance(6u32); // searchRange, entrySelector, rangeShift
+
+        for _ in 0..num_tables {
+            f(TableRecord {
+                tag: s.read()?,
+                check_sum: s.read()?,
+                offset: s.read()?,
+                length: s.read()?,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn table_record(&self, tag: u32) -> Result<TableRecord> {
+        let mut found = None;
+        self.for_each_table_record(|record| {
+            if record.tag == tag && found.is_none() {
+                found = Some(record);
+            }
+
+            Ok(())
+        })?;
+
+        found.ok_or(Error::TableMissing(tag))
+    }
+}
+
+fn table_checksum_for(data: &[u8], record: &TableRecord) -> Result<u32> {
+    let table = data.try_slice(record.offset as usize..(record.offset as usize + record.length as usize), 0)?;
+
+    if record.tag == HEAD_TABLE_TAG {
+        // The `checkSumAdjustment` field must be treated as zero. Report
+        // out-of-bounds errors relative to the whole file, not to this
+        // table's own slice.
+        let adjustment: u32 = Stream::read_at_offset(
+            table,
+            HEAD_CHECKSUM_ADJUSTMENT_OFFSET,
+            record.offset as usize,
+        )?;
+        Ok(table_checksum(table).wrapping_sub(adjustment))
+    } else {
+        Ok(table_checksum(table))
+    }
+}
+
+/// Computes an OpenType table checksum.
+///
+/// Table bytes are treated as a sequence of big-endian `u32` words and
+/// accumulated with wrapping addition. If `data.len()` is not a multiple
+/// of four, the trailing bytes are zero-padded before the final word.
+fn table_checksum(data: &[u8]) -> u32 {
+    let mut sum: u32 = 0;
+    let mut chunks = data.chunks_exact(4);
+
+    for chunk in &mut chunks {
+        sum = sum.wrapping_add(u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        let mut last = [0u8; 4];
+        last[..remainder.len()].copy_from_slice(remainder);
+        sum = sum.wrapping_add(u32::from_be_bytes(last));
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_big_endian_words() {
+        let data = [0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02];
+        assert_eq!(table_checksum(&data), 3);
+    }
+
+    #[test]
+    fn zero_pads_trailing_bytes() {
+        // Trailing `00 00 01` is padded with a zero byte to `00 00 01 00`.
+        let data = [0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01];
+        assert_eq!(table_checksum(&data), 1 + 0x100);
+    }
+
+    #[test]
+    fn wraps_on_overflow() {
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x02];
+        assert_eq!(table_checksum(&data), 1);
+    }
+
+    #[test]
+    fn head_table_treats_checksum_adjustment_as_zero() {
+        let mut head = [0u8; 16];
+        head[HEAD_CHECKSUM_ADJUSTMENT_OFFSET..HEAD_CHECKSUM_ADJUSTMENT_OFFSET + 4]
+            .copy_from_slice(&0xDEAD_BEEFu32.to_be_bytes());
+
+        let record = TableRecord {
+            tag: HEAD_TABLE_TAG,
+            check_sum: 0,
+            offset: 0,
+            length: head.len() as u32,
+        };
+
+        let sum = table_checksum_for(&head, &record).unwrap();
+        assert_eq!(sum, table_checksum(&head).wrapping_sub(0xDEAD_BEEF));
+    }
+
+    #[test]
+    fn truncated_head_table_reports_a_file_absolute_offset() {
+        // A 104-byte file whose `head` table starts at offset 100 but is
+        // only 4 bytes long - too short to hold `checkSumAdjustment` at
+        // offset 8 into the table.
+        let data = [0u8; 104];
+        let record = TableRecord {
+            tag: HEAD_TABLE_TAG,
+            check_sum: 0,
+            offset: 100,
+            length: 4,
+        };
+
+        match table_checksum_for(&data, &record) {
+            Err(Error::SliceOutOfBounds { offset, .. }) => {
+                assert_eq!(offset, 100 + HEAD_CHECKSUM_ADJUSTMENT_OFFSET);
+            }
+            other => panic!("expected SliceOutOfBounds, got {:?}", other),
+        }
+    }
+}
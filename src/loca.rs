@@ -2,13 +2,15 @@
 
 use core::ops::Range;
 
-use crate::parser::{Stream, LazyArray};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::parser::{Stream, StreamWriter, LazyArray, ToData};
+use crate::head::IndexToLocationFormat as Format;
 use crate::{Font, GlyphId, Result};
 
 impl<'a> Font<'a> {
     pub(crate) fn glyph_range(&self, glyph_id: GlyphId) -> Result<Option<Range<usize>>> {
-        use crate::head::IndexToLocationFormat as Format;
-
         // Check for overflow.
         if self.number_of_glyphs() == core::u16::MAX {
             return Ok(None);
@@ -52,3 +54,80 @@ impl<'a> Font<'a> {
         }
     }
 }
+
+/// Builds a `loca` table from an ordered list of per-glyph outline lengths.
+///
+/// This is the inverse of `Font::glyph_range`: given `glyph_lengths[i]`,
+/// the number of `glyf` bytes belonging to glyph `i`, produces the
+/// `number_of_glyphs + 1` cumulative offsets that `loca` stores, picking
+/// `Short` format when every offset is even and fits (once halved) into
+/// a `u16`, and `Long` format otherwise.
+pub(crate) fn build_loca(glyph_lengths: &[u32]) -> (Vec<u8>, Format) {
+    let mut offsets = Vec::with_capacity(glyph_lengths.len() + 1);
+    let mut offset: u32 = 0;
+    offsets.push(offset);
+    for &len in glyph_lengths {
+        offset += len;
+        offsets.push(offset);
+    }
+
+    let fits_short = offsets.iter().all(|&o| o % 2 == 0 && o / 2 <= core::u16::MAX as u32);
+    let format = if fits_short { Format::Short } else { Format::Long };
+
+    let entry_size = match format {
+        Format::Short => u16::SIZE,
+        Format::Long => u32::SIZE,
+    };
+
+    let mut data = vec![0u8; offsets.len() * entry_size];
+    let mut w = StreamWriter::new(&mut data);
+    match format {
+        Format::Short => {
+            for &o in &offsets {
+                w.write(&((o / 2) as u16));
+            }
+        }
+        Format::Long => {
+            for &o in &offsets {
+                w.write(&o);
+            }
+        }
+    }
+
+    (data, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_short_format_for_even_offsets_within_range() {
+        let (data, format) = build_loca(&[4, 6]);
+        assert_eq!(format, Format::Short);
+        // 3 cumulative offsets (0, 4, 10), each halved and stored as u16.
+        assert_eq!(data, [0, 0, 0, 2, 0, 5]);
+    }
+
+    #[test]
+    fn picks_long_format_for_odd_offsets() {
+        // An odd glyph length makes a cumulative offset odd, which can't
+        // be represented by `Short` (offset / 2 must be exact).
+        let (_, format) = build_loca(&[3]);
+        assert_eq!(format, Format::Long);
+    }
+
+    #[test]
+    fn picks_short_format_exactly_at_the_u16_boundary() {
+        let max_short_offset = core::u16::MAX as u32 * 2;
+        let (_, format) = build_loca(&[max_short_offset]);
+        assert_eq!(format, Format::Short);
+    }
+
+    #[test]
+    fn picks_long_format_just_past_the_u16_boundary() {
+        let just_over = core::u16::MAX as u32 * 2 + 2;
+        let (_, format) = build_loca(&[just_over]);
+        assert_eq!(format, Format::Long);
+    }
+}
@@ -107,6 +107,85 @@ impl FromData for Fixed {
 }
 
 
+pub trait ToData: Sized {
+    /// Stores an object size in raw data.
+    ///
+    /// `mem::size_of` by default.
+    ///
+    /// Override when size of `Self` != size of a raw data.
+    const SIZE: usize = core::mem::size_of::<Self>();
+
+    /// Writes an object as raw data.
+    ///
+    /// `out.len()` is guaranteed to be exactly `Self::SIZE`.
+    fn write(&self, out: &mut [u8]);
+}
+
+impl ToData for u8 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        out[0] = *self;
+    }
+}
+
+impl ToData for i16 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToData for u16 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToData for u32 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToData for i32 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToData for U24 {
+    const SIZE: usize = 3;
+
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        out[0] = (self.0 >> 16) as u8;
+        out[1] = (self.0 >> 8) as u8;
+        out[2] = self.0 as u8;
+    }
+}
+
+impl ToData for F2DOT14 {
+    const SIZE: usize = 2;
+
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        ((self.0 * 16384.0).round() as i16).write(out)
+    }
+}
+
+impl ToData for Fixed {
+    const SIZE: usize = 4;
+
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        ((self.0 * 65536.0).round() as i32).write(out)
+    }
+}
+
+
 pub trait TryFromData: Sized {
     /// Stores an object size in raw data.
     ///
@@ -188,27 +267,59 @@ impl<'a, T: FromData> LazyArray<'a, T> {
         self.len() == 0
     }
 
+    /// Returns the matched value along with its index, so that callers
+    /// who also need the position (e.g. to look up a parallel array)
+    /// don't have to search twice.
     #[inline]
-    pub fn binary_search(&self, x: &T) -> Option<T>
+    pub fn binary_search(&self, x: &T) -> Option<(u32, T)>
         where T: Ord
     {
         self.binary_search_by(|p| p.cmp(x))
     }
 
-    #[inline]
-    pub fn binary_search_by<F>(&self, mut f: F) -> Option<T>
+    /// Same as `binary_search`, but with a custom comparator.
+    ///
+    /// The array is assumed to already be sorted according to `f`'s
+    /// ordering, as required by the spec for the table being searched.
+    /// In debug builds this is checked by replaying `f` over every entry
+    /// and asserting its results never regress (Less -> Equal -> Greater),
+    /// which tolerates tables sorted by only one field of `T` - unlike
+    /// checking `T`'s own `Ord`, which would also compare the other,
+    /// freely-varying fields and could panic on a perfectly valid font.
+    pub fn binary_search_by<F>(&self, mut f: F) -> Option<(u32, T)>
         where F: FnMut(&T) -> core::cmp::Ordering
     {
         // Based on Rust std implementation.
 
         use core::cmp::Ordering;
 
-        let mut size = self.len() as u32;
+        let size = self.len() as u32;
         if size == 0 {
             return None;
         }
 
+        #[cfg(debug_assertions)]
+        {
+            let rank = |o: Ordering| match o {
+                Ordering::Less => 0u8,
+                Ordering::Equal => 1,
+                Ordering::Greater => 2,
+            };
+
+            let mut prev_rank = 0u8;
+            for i in 0..size {
+                let current_rank = rank(f(&self.at(i)));
+                debug_assert!(
+                    current_rank >= prev_rank,
+                    "LazyArray is not sorted relative to the search key: entry {} is out of order",
+                    i,
+                );
+                prev_rank = current_rank;
+            }
+        }
+
         let mut base = 0;
+        let mut size = size;
         while size > 1 {
             let half = size / 2;
             let mid = base + half;
@@ -223,7 +334,7 @@ impl<'a, T: FromData> LazyArray<'a, T> {
         // base is always in [0, size) because base <= mid.
         let value = self.at(base);
         let cmp = f(&value);
-        if cmp == Ordering::Equal { Some(value) } else { None }
+        if cmp == Ordering::Equal { Some((base, value)) } else { None }
     }
 }
 
@@ -269,19 +380,27 @@ impl<'a, T: FromData> Iterator for LazyArrayIter<'a, T> {
 
 
 pub trait TrySlice<'a> {
-    fn try_slice(&self, range: Range<usize>) -> Result<&'a [u8]>;
-    fn try_slice_from<T: Offset>(&self, start: T) -> Result<&'a [u8]>;
+    fn try_slice(&self, range: Range<usize>, base_offset: usize) -> Result<&'a [u8]>;
+    fn try_slice_from<T: Offset>(&self, start: T, base_offset: usize) -> Result<&'a [u8]>;
 }
 
 impl<'a> TrySlice<'a> for &'a [u8] {
     #[inline]
-    fn try_slice(&self, range: Range<usize>) -> Result<&'a [u8]> {
-        self.get(range.clone()).ok_or_else(|| Error::SliceOutOfBounds)
+    fn try_slice(&self, range: Range<usize>, base_offset: usize) -> Result<&'a [u8]> {
+        let length = range.end.saturating_sub(range.start);
+        self.get(range.clone()).ok_or(Error::SliceOutOfBounds {
+            offset: base_offset + range.start,
+            length,
+        })
     }
 
     #[inline]
-    fn try_slice_from<T: Offset>(&self, start: T) -> Result<&'a [u8]> {
-        self.get(start.to_usize()..).ok_or_else(|| Error::SliceOutOfBounds)
+    fn try_slice_from<T: Offset>(&self, start: T, base_offset: usize) -> Result<&'a [u8]> {
+        let start = start.to_usize();
+        self.get(start..).ok_or(Error::SliceOutOfBounds {
+            offset: base_offset + start,
+            length: self.len().saturating_sub(start),
+        })
     }
 }
 
@@ -290,6 +409,10 @@ impl<'a> TrySlice<'a> for &'a [u8] {
 pub struct Stream<'a> {
     data: &'a [u8],
     offset: usize,
+    // The file-absolute offset of `data[0]`, so that out-of-bounds errors
+    // can report a position relative to the whole font rather than to
+    // whatever table slice this stream happens to be reading.
+    base_offset: usize,
 }
 
 impl<'a> Stream<'a> {
@@ -298,14 +421,30 @@ impl<'a> Stream<'a> {
         Stream {
             data,
             offset: 0,
+            base_offset: 0,
         }
     }
 
+    /// Creates a `Stream` over `data` with the read cursor starting at
+    /// `offset` bytes into it.
     #[inline]
     pub fn new_at(data: &'a [u8], offset: usize) -> Self {
         Stream {
             data,
             offset,
+            base_offset: 0,
+        }
+    }
+
+    /// Creates a `Stream` over `data`, a slice that begins `base_offset`
+    /// bytes into the whole font file, so that out-of-bounds errors
+    /// report a file-absolute position instead of a table-relative one.
+    #[inline]
+    pub fn new_at_offset(data: &'a [u8], base_offset: usize) -> Self {
+        Stream {
+            data,
+            offset: 0,
+            base_offset,
         }
     }
 
@@ -319,9 +458,22 @@ impl<'a> Stream<'a> {
         self.offset
     }
 
+    /// Marks the current position. Pair with `span_since` to recover the
+    /// file-absolute byte range consumed between the two points.
+    #[inline]
+    pub fn mark(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the file-absolute byte range consumed since `mark`.
+    #[inline]
+    pub fn span_since(&self, mark: usize) -> Range<usize> {
+        (self.base_offset + mark)..(self.base_offset + self.offset)
+    }
+
     #[inline]
     pub fn tail(&self) -> Result<&'a [u8]> {
-        self.data.try_slice(self.offset..self.data.len())
+        self.data.try_slice(self.offset..self.data.len(), self.base_offset)
     }
 
     #[inline]
@@ -340,7 +492,7 @@ impl<'a> Stream<'a> {
         self.offset += T::SIZE;
         let end = self.offset;
 
-        let data = self.data.try_slice(start..end)?;
+        let data = self.data.try_slice(start..end, self.base_offset)?;
         Ok(T::parse(data))
     }
 
@@ -350,17 +502,24 @@ impl<'a> Stream<'a> {
         self.offset += T::SIZE;
         let end = self.offset;
 
-        let data = self.data.try_slice(start..end)?;
+        let data = self.data.try_slice(start..end, self.base_offset)?;
         T::try_parse(data)
     }
 
     #[inline]
-    pub fn read_at<T: FromData>(data: &[u8], mut offset: usize) -> Result<T> {
+    pub fn read_at<T: FromData>(data: &[u8], offset: usize) -> Result<T> {
+        Self::read_at_offset(data, offset, 0)
+    }
+
+    /// Same as `read_at`, but reports out-of-bounds errors relative to
+    /// `base_offset` bytes into the whole font file rather than to `data`.
+    #[inline]
+    pub fn read_at_offset<T: FromData>(data: &[u8], mut offset: usize, base_offset: usize) -> Result<T> {
         let start = offset;
         offset += T::SIZE;
         let end = offset;
 
-        let data = data.try_slice(start..end)?;
+        let data = data.try_slice(start..end, base_offset)?;
         Ok(T::parse(data))
     }
 
@@ -368,7 +527,7 @@ impl<'a> Stream<'a> {
     pub fn read_bytes<L: FSize>(&mut self, len: L) -> Result<&'a [u8]> {
         let offset = self.offset;
         self.offset += len.to_usize();
-        self.data.try_slice(offset..(offset + len.to_usize()))
+        self.data.try_slice(offset..(offset + len.to_usize()), self.base_offset)
     }
 
     #[inline]
@@ -389,6 +548,50 @@ impl<'a> Stream<'a> {
         let count: u32 = self.read()?;
         self.read_array(count)
     }
+
+    /// Reads a `u16`-prefixed array, validating every entry with
+    /// `TryFromData` up front instead of lazily on each `at()`.
+    #[inline]
+    pub fn try_read_array16<T: TryFromData + FromData>(&mut self) -> Result<LazyArray<'a, T>> {
+        let count: u16 = self.read()?;
+        self.try_read_array(count)
+    }
+
+    /// Same as `try_read_array16`, but with a `u32` count prefix.
+    #[inline]
+    pub fn try_read_array32<T: TryFromData + FromData>(&mut self) -> Result<LazyArray<'a, T>> {
+        let count: u32 = self.read()?;
+        self.try_read_array(count)
+    }
+
+    fn try_read_array<T: TryFromData + FromData, L: FSize>(&mut self, len: L) -> Result<LazyArray<'a, T>> {
+        let size = <T as TryFromData>::SIZE;
+        let count = len.to_usize();
+        let byte_len = match count.checked_mul(size) {
+            Some(n) if n <= core::u32::MAX as usize => n,
+            _ => return Err(Error::SliceOutOfBounds {
+                offset: self.base_offset + self.offset,
+                length: count.saturating_mul(size),
+            }),
+        };
+
+        let data = self.read_bytes(byte_len as u32)?;
+
+        // Validate only as many entries as the slice we actually got,
+        // never the raw (possibly huge, attacker-controlled) `count` -
+        // otherwise a `count * T::SIZE` that wrapped past `u32::MAX`
+        // would leave `data` short while this loop still ran to `count`,
+        // indexing past the end of `data` and panicking.
+        for i in 0..(data.len() / size) {
+            let start = i * size;
+            let end = start + size;
+            if T::try_parse(&data[start..end]).is_err() {
+                return Err(Error::InvalidArrayElement(i));
+            }
+        }
+
+        Ok(LazyArray::new(data))
+    }
 }
 
 
@@ -432,6 +635,52 @@ impl<'a> SafeStream<'a> {
 }
 
 
+/// The write-side counterpart of `Stream`.
+///
+/// Writes `ToData` values into a caller-provided buffer, keeping track
+/// of how many bytes have been written so far.
+pub struct StreamWriter<'a> {
+    data: &'a mut [u8],
+    offset: usize,
+}
+
+impl<'a> StreamWriter<'a> {
+    #[inline]
+    pub fn new(data: &'a mut [u8]) -> Self {
+        StreamWriter {
+            data,
+            offset: 0,
+        }
+    }
+
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[inline]
+    pub fn write<T: ToData>(&mut self, value: &T) {
+        let start = self.offset;
+        self.offset += T::SIZE;
+        value.write(&mut self.data[start..self.offset]);
+    }
+
+    #[inline]
+    pub fn write_array<T: ToData>(&mut self, values: &[T]) {
+        for value in values {
+            self.write(value);
+        }
+    }
+
+    #[inline]
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        let start = self.offset;
+        self.offset += bytes.len();
+        self.data[start..self.offset].copy_from_slice(bytes);
+    }
+}
+
+
 pub trait Offset {
     fn to_usize(&self) -> usize;
     fn is_null(&self) -> bool { self.to_usize() == 0 }
@@ -454,6 +703,13 @@ impl FromData for Offset16 {
     }
 }
 
+impl ToData for Offset16 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        self.0.write(out)
+    }
+}
+
 impl FromData for Option<Offset16> {
     const SIZE: usize = Offset16::SIZE;
 
@@ -481,6 +737,13 @@ impl FromData for Offset32 {
     }
 }
 
+impl ToData for Offset32 {
+    #[inline]
+    fn write(&self, out: &mut [u8]) {
+        self.0.write(out)
+    }
+}
+
 impl FromData for Option<Offset32> {
     const SIZE: usize = Offset32::SIZE;
 
@@ -498,3 +761,144 @@ pub struct Offsets<'a, T: Offset> {
     data: &'a [u8],
     offsets: LazyArray<'a, T>, // [Offset16/Offset32]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_search_returns_index_alongside_value() {
+        let raw: [u8; 12] = [0, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0, 30];
+        let array: LazyArray<u32> = LazyArray::new(&raw);
+
+        let (index, value) = array.binary_search(&20).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(value, 20);
+
+        assert!(array.binary_search(&99).is_none());
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct KeyedEntry {
+        key: u16,
+        payload: u16,
+    }
+
+    impl FromData for KeyedEntry {
+        const SIZE: usize = 4;
+
+        fn parse(data: &[u8]) -> Self {
+            KeyedEntry {
+                key: u16::parse(&data[0..2]),
+                payload: u16::parse(&data[2..4]),
+            }
+        }
+    }
+
+    #[test]
+    fn binary_search_by_tolerates_non_key_field_regressions() {
+        // Sorted by `key`, but `payload` regresses between two entries
+        // that share a key - a derived, all-fields `Ord` would see this
+        // as out of order even though the table is spec-compliant.
+        let raw: [u8; 12] = [
+            0, 1, 0, 9, // key=1 payload=9
+            0, 1, 0, 3, // key=1 payload=3
+            0, 2, 0, 5, // key=2 payload=5
+        ];
+        let array: LazyArray<KeyedEntry> = LazyArray::new(&raw);
+
+        let (index, value) = array.binary_search_by(|e| e.key.cmp(&2)).unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(value.payload, 5);
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct NonZeroU16(u16);
+
+    impl FromData for NonZeroU16 {
+        const SIZE: usize = 2;
+
+        fn parse(data: &[u8]) -> Self {
+            NonZeroU16(u16::parse(data))
+        }
+    }
+
+    impl TryFromData for NonZeroU16 {
+        const SIZE: usize = 2;
+
+        fn try_parse(data: &[u8]) -> Result<Self> {
+            let value = u16::parse(data);
+            if value == 0 {
+                // The actual index reported to the caller comes from
+                // `try_read_array`'s own loop, not from here.
+                Err(Error::InvalidArrayElement(0))
+            } else {
+                Ok(NonZeroU16(value))
+            }
+        }
+    }
+
+    #[test]
+    fn try_read_array16_validates_every_entry_up_front() {
+        // count = 3, followed by three non-zero u16 entries.
+        let raw: [u8; 8] = [0, 3, 0, 1, 0, 2, 0, 3];
+        let mut s = Stream::new(&raw);
+        let array: LazyArray<NonZeroU16> = s.try_read_array16().unwrap();
+
+        assert_eq!(array.len(), 3);
+        assert_eq!(array.at(0u32), NonZeroU16(1));
+        assert_eq!(array.at(2u32), NonZeroU16(3));
+    }
+
+    #[test]
+    fn try_read_array16_reports_first_bad_index() {
+        // count = 3, but the second entry is zero, which `NonZeroU16` rejects.
+        let raw: [u8; 8] = [0, 3, 0, 1, 0, 0, 0, 3];
+        let mut s = Stream::new(&raw);
+        let result: Result<LazyArray<NonZeroU16>> = s.try_read_array16();
+
+        match result {
+            Err(Error::InvalidArrayElement(index)) => assert_eq!(index, 1),
+            other => panic!("expected InvalidArrayElement(1), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn try_read_array32_does_not_panic_on_a_count_that_overflows_u32_bytes() {
+        // count = 0x8000_0000 entries of this 2-byte type: count * SIZE
+        // is exactly 2^32, which wraps to 0 as a `u32` byte length. The
+        // fix must reject this instead of reading an empty slice and
+        // then indexing into it `count` times.
+        let raw: [u8; 4] = [0x80, 0x00, 0x00, 0x00];
+        let mut s = Stream::new(&raw);
+        let result: Result<LazyArray<NonZeroU16>> = s.try_read_array32();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn new_at_offset_reports_file_absolute_offsets_on_oob_reads() {
+        let data = [0u8; 4];
+        let mut s = Stream::new_at_offset(&data, 100);
+        s.advance(2u32);
+
+        let result: Result<u32> = s.read();
+        match result {
+            Err(Error::SliceOutOfBounds { offset, length }) => {
+                assert_eq!(offset, 102);
+                assert_eq!(length, 4);
+            }
+            other => panic!("expected SliceOutOfBounds, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mark_and_span_since_report_file_absolute_range() {
+        let data = [0u8; 8];
+        let mut s = Stream::new_at_offset(&data, 100);
+        s.advance(2u32);
+        let mark = s.mark();
+        s.advance(3u32);
+
+        assert_eq!(s.span_since(mark), 102..105);
+    }
+}